@@ -0,0 +1,76 @@
+use datafusion::catalog::TableProvider;
+use datafusion::datasource::provider_as_source;
+use datafusion_expr::{LogicalPlan, LogicalPlanBuilder};
+use std::sync::Arc;
+use vegafusion_common::data::table::VegaFusionTable;
+use vegafusion_common::error::{Result, VegaFusionError};
+
+/// An inline dataset registered with the runtime ahead of evaluation (e.g. via
+/// `pre_transform_spec`'s `inline_datasets` map), in either of the two shapes a caller may already
+/// have the data in: a fully materialized [`VegaFusionTable`], or a live DataFusion
+/// [`TableProvider`] the runtime should scan directly rather than eagerly collecting into a
+/// table first. The `TableProvider` variant matters for providers backed by I/O (a Parquet
+/// directory, a remote catalog, ...) where collecting up front would throw away pushdown
+/// opportunities the rest of the plan could otherwise take advantage of.
+///
+/// `table_scan` is the entry point the runtime's `table://` dataset resolution is expected to
+/// call for each inline dataset to build the initial `TableScan` node of a spec's data pipeline;
+/// that resolution lives in `task_graph::runtime::VegaFusionRuntime`, outside this crate.
+#[derive(Clone)]
+pub enum VegaFusionDataset {
+    Table(VegaFusionTable),
+    Provider(Arc<dyn TableProvider>),
+}
+
+impl VegaFusionDataset {
+    /// Wraps an already-materialized table as an inline dataset.
+    pub fn from_table(table: VegaFusionTable) -> Self {
+        Self::Table(table)
+    }
+
+    /// Wraps a live `TableProvider` as an inline dataset, so the runtime scans it directly
+    /// instead of collecting it into a `VegaFusionTable` up front.
+    pub fn from_table_provider(provider: Arc<dyn TableProvider>) -> Self {
+        Self::Provider(provider)
+    }
+
+    /// Returns this dataset's `TableProvider`, materializing the `Table` variant into a
+    /// `MemTable`-backed provider on demand.
+    pub fn table_provider(&self) -> Result<Arc<dyn TableProvider>> {
+        match self {
+            Self::Provider(provider) => Ok(provider.clone()),
+            Self::Table(table) => {
+                let mem_table = datafusion::datasource::MemTable::try_new(
+                    table.schema.clone(),
+                    vec![table.batches.clone()],
+                )
+                .map_err(|e| {
+                    VegaFusionError::internal(format!(
+                        "Failed to build MemTable for inline dataset: {e}"
+                    ))
+                })?;
+                Ok(Arc::new(mem_table))
+            }
+        }
+    }
+
+    /// Builds a bare `LogicalPlan::TableScan` named `table_name` against this dataset's
+    /// `TableProvider`, ready to have transforms layered on top.
+    pub fn table_scan(&self, table_name: &str) -> Result<LogicalPlan> {
+        let provider = self.table_provider()?;
+        let source = provider_as_source(provider);
+        LogicalPlanBuilder::scan(table_name, source, None)
+            .and_then(|b| b.build())
+            .map_err(|e| {
+                VegaFusionError::internal(format!(
+                    "Failed to build TableScan for inline dataset {table_name}: {e}"
+                ))
+            })
+    }
+}
+
+impl From<VegaFusionTable> for VegaFusionDataset {
+    fn from(table: VegaFusionTable) -> Self {
+        Self::Table(table)
+    }
+}
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion_expr::LogicalPlan;
+use futures::stream;
+use vegafusion_common::arrow::datatypes::SchemaRef;
+use vegafusion_common::data::table::VegaFusionTable;
+use vegafusion_common::error::{Result, VegaFusionError};
+
+/// Executes a compiled `LogicalPlan` on behalf of the runtime. The default execution path runs
+/// plans against an in-process DataFusion `SessionContext` (see
+/// `vegafusion_runtime::plan_executor::DataFusionPlanExecutor`), but a caller embedding
+/// VegaFusion inside a larger system (a notebook kernel, a warehouse-backed service, ...) can
+/// supply its own `PlanExecutor` to run plans against whatever engine it already manages --
+/// forwarding generated SQL to a remote connection, routing through its own query scheduler, etc.
+#[async_trait]
+pub trait PlanExecutor: Send + Sync {
+    /// Runs `plan` to completion and returns its full result as a single table.
+    async fn execute_plan(&self, plan: LogicalPlan) -> Result<VegaFusionTable>;
+
+    /// Like `execute_plan`, but returns the result as a stream of record batches instead of
+    /// collecting it into a single table first. `pre_transform_extract` drives this so a large
+    /// chart's rows can be counted against its `extract_threshold` and spilled to the extracted
+    /// dataset as they arrive, rather than forcing the whole transformed dataset into memory up
+    /// front. The default collects via `execute_plan` and replays the result as a one-shot
+    /// stream, which is always correct but gives up that early-exit opportunity; an executor
+    /// backed by a real streaming engine should override this.
+    async fn execute_plan_stream(&self, plan: LogicalPlan) -> Result<SendableRecordBatchStream> {
+        let table = self.execute_plan(plan).await?;
+        let schema: SchemaRef = table.schema.clone();
+        let batches = table.batches.clone();
+        let stream = stream::iter(batches.into_iter().map(Ok));
+        Ok(Box::pin(
+            datafusion::physical_plan::stream::RecordBatchStreamAdapter::new(schema, stream),
+        ))
+    }
+}
+
+/// A `PlanExecutor` that always fails -- the default when a caller hasn't wired up a real
+/// execution backend (e.g. constructing a `VegaFusionRuntimeTrait` implementor directly rather
+/// than through its normal constructor), so the failure is immediate and clear rather than a
+/// confusing downstream panic.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoOpPlanExecutor;
+
+#[async_trait]
+impl PlanExecutor for NoOpPlanExecutor {
+    async fn execute_plan(&self, _plan: LogicalPlan) -> Result<VegaFusionTable> {
+        Err(VegaFusionError::internal(
+            "No PlanExecutor configured: NoOpPlanExecutor cannot execute plans",
+        ))
+    }
+}
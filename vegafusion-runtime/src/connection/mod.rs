@@ -0,0 +1,70 @@
+use crate::sql::{logical_plan_to_sql, SqlDialect};
+use async_trait::async_trait;
+use datafusion_expr::{LogicalPlan, LogicalPlanBuilder, TableSource};
+use std::any::Any;
+use std::sync::Arc;
+use vegafusion_common::arrow::datatypes::SchemaRef;
+use vegafusion_common::data::table::VegaFusionTable;
+use vegafusion_common::error::Result;
+
+/// A connection to an external SQL engine (Spark, a warehouse, ...) that work can be pushed down
+/// to instead of materializing data through the local `SessionContext`.
+///
+/// Unlike the local path, a `RemoteConnection` doesn't require its tables to already be
+/// registered in-process: `resolve_schema` looks up a table's schema asynchronously (e.g. a
+/// `DESCRIBE`/catalog call against the remote engine), so a logical plan scanning that table can
+/// be built -- and then unparsed via [`logical_plan_to_sql`] -- without ever materializing the
+/// table locally. Only the final, reduced result returned by `execute_sql` crosses back into
+/// VegaFusion.
+#[async_trait]
+pub trait RemoteConnection: Send + Sync {
+    /// The dialect this connection's engine expects generated SQL to be written in.
+    fn dialect(&self) -> &dyn SqlDialect;
+
+    /// Resolve the schema of `table_name` against the remote engine.
+    async fn resolve_schema(&self, table_name: &str) -> Result<SchemaRef>;
+
+    /// Run `sql` against the remote engine and return the result.
+    async fn execute_sql(&self, sql: &str) -> Result<VegaFusionTable>;
+}
+
+/// A `TableSource` standing in for a table that lives on the remote engine -- schema-only, like
+/// `sql::cte`'s `CteTableSource`, since the actual rows are never materialized locally.
+#[derive(Debug)]
+struct RemoteTableSource {
+    schema: SchemaRef,
+}
+
+impl TableSource for RemoteTableSource {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Builds a bare `LogicalPlan::TableScan` over `table_name` after resolving its schema through
+/// `connection`, ready to have further transforms layered on top and then pushed down whole via
+/// [`plan_to_remote_result`].
+pub async fn scan_remote_table(
+    connection: &dyn RemoteConnection,
+    table_name: &str,
+) -> Result<LogicalPlan> {
+    let schema = connection.resolve_schema(table_name).await?;
+    let source = Arc::new(RemoteTableSource { schema });
+    Ok(LogicalPlanBuilder::scan(table_name, source, None)?.build()?)
+}
+
+/// Unparses `plan` for `connection`'s dialect and executes it remotely, returning the final
+/// result table. This is the SQL-pushdown counterpart to evaluating a compiled
+/// `TransformPipeline` locally via `TransformPipelineUtils::eval_sql`: the whole pipeline is
+/// compiled into one logical plan, unparsed once, and run entirely in the warehouse.
+pub async fn plan_to_remote_result(
+    connection: &dyn RemoteConnection,
+    plan: &LogicalPlan,
+) -> Result<VegaFusionTable> {
+    let sql = logical_plan_to_sql(plan, connection.dialect())?;
+    connection.execute_sql(&sql).await
+}
@@ -1,9 +1,34 @@
 use datafusion_common::tree_node::{Transformed, TreeNodeRewriter};
 use datafusion_expr::{col, Expr};
 
+/// Metadata describing a window function lifted out of an expression tree by
+/// [`PureAggRewriter`] -- enough for the caller to reconstruct the equivalent
+/// `LogicalPlan::Window` node (partition-by, order-by, and the aliased window
+/// expression itself, which also carries its own frame).
+#[derive(Debug, Clone)]
+pub struct PureWindowSpec {
+    /// The window expression, aliased to the generated `_win_N` name.
+    pub expr: Expr,
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<Expr>,
+}
+
+/// Lifts pure aggregate and window function calls out of an expression tree, replacing each
+/// with a reference to a generated column (`_agg_N`/`_win_N`). This lets a caller split a single
+/// expression that mixes aggregates/window functions with other computation into a
+/// `LogicalPlan::Aggregate`/`LogicalPlan::Window` that computes the lifted pieces, plus a
+/// `LogicalPlan::Projection` that recombines them.
+///
+/// Aggregates with `distinct = true` are lifted the same way as any other `AggregateFunction` --
+/// the `distinct` flag travels with the lifted expression, so `count(distinct x)` still runs as a
+/// distinct aggregate once pushed down through the extraction machinery. Window functions
+/// (ranking, `row_number`, etc.) are lifted analogously, with their partition-by/order-by keys
+/// recorded in `pure_windows` so the caller can rebuild the `Window` node.
 pub struct PureAggRewriter {
     pub pure_aggs: Vec<Expr>,
-    pub next_id: usize,
+    pub pure_windows: Vec<PureWindowSpec>,
+    next_agg_id: usize,
+    next_win_id: usize,
 }
 
 impl Default for PureAggRewriter {
@@ -16,13 +41,21 @@ impl PureAggRewriter {
     pub fn new() -> Self {
         Self {
             pure_aggs: vec![],
-            next_id: 0,
+            pure_windows: vec![],
+            next_agg_id: 0,
+            next_win_id: 0,
         }
     }
 
     fn new_agg_name(&mut self) -> String {
-        let name = format!("_agg_{}", self.next_id);
-        self.next_id += 1;
+        let name = format!("_agg_{}", self.next_agg_id);
+        self.next_agg_id += 1;
+        name
+    }
+
+    fn new_win_name(&mut self) -> String {
+        let name = format!("_win_{}", self.next_win_id);
+        self.next_win_id += 1;
         name
     }
 }
@@ -31,15 +64,37 @@ impl TreeNodeRewriter for PureAggRewriter {
     type Node = Expr;
 
     fn f_down(&mut self, node: Expr) -> datafusion_common::Result<Transformed<Self::Node>> {
-        if let Expr::AggregateFunction(agg) = node {
-            // extract agg and replace with column
-            let name = self.new_agg_name();
-            self.pure_aggs
-                .push(Expr::AggregateFunction(agg).alias(&name));
-            Ok(Transformed::new_transformed(col(name), true))
-        } else {
-            // Return expr node unchanged
-            Ok(Transformed::no(node))
+        match node {
+            Expr::AggregateFunction(agg) => {
+                // extract agg (distinct or not) and replace with column
+                let name = self.new_agg_name();
+                self.pure_aggs
+                    .push(Expr::AggregateFunction(agg).alias(&name));
+                Ok(Transformed::new_transformed(col(name), true))
+            }
+            Expr::WindowFunction(window_fn) => {
+                // extract window function and replace with column, recording enough metadata
+                // (partition-by/order-by) for the caller to rebuild the `Window` node
+                let partition_by = window_fn.params.partition_by.clone();
+                let order_by = window_fn
+                    .params
+                    .order_by
+                    .iter()
+                    .map(|sort| sort.expr.clone())
+                    .collect();
+                let name = self.new_win_name();
+                let windowed_expr = Expr::WindowFunction(window_fn).alias(&name);
+                self.pure_windows.push(PureWindowSpec {
+                    expr: windowed_expr,
+                    partition_by,
+                    order_by,
+                });
+                Ok(Transformed::new_transformed(col(name), true))
+            }
+            other => {
+                // Return expr node unchanged
+                Ok(Transformed::no(other))
+            }
         }
     }
 }
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use datafusion::physical_plan::{execute_stream, SendableRecordBatchStream};
+use datafusion::prelude::{DataFrame, SessionContext};
+use datafusion_expr::LogicalPlan;
+use std::sync::Arc;
+use vegafusion_core::data::util::DataFrameUtils;
+use vegafusion_core::runtime::PlanExecutor;
+use vegafusion_common::data::table::VegaFusionTable;
+use vegafusion_common::error::{Result, VegaFusionError};
+
+/// The default [`PlanExecutor`]: runs a plan against an in-process DataFusion `SessionContext`,
+/// the same engine the rest of this crate already uses to evaluate transforms locally. Plugged
+/// in by `VegaFusionRuntime`'s normal constructor whenever a caller doesn't supply their own
+/// `PlanExecutor`.
+pub struct DataFusionPlanExecutor {
+    ctx: Arc<SessionContext>,
+}
+
+impl DataFusionPlanExecutor {
+    pub fn new(ctx: Arc<SessionContext>) -> Self {
+        Self { ctx }
+    }
+}
+
+#[async_trait]
+impl PlanExecutor for DataFusionPlanExecutor {
+    async fn execute_plan(&self, plan: LogicalPlan) -> Result<VegaFusionTable> {
+        let df = DataFrame::new(self.ctx.state(), plan);
+        df.collect_to_table().await
+    }
+
+    async fn execute_plan_stream(&self, plan: LogicalPlan) -> Result<SendableRecordBatchStream> {
+        let state = self.ctx.state();
+        let physical_plan = state.create_physical_plan(&plan).await.map_err(|e| {
+            VegaFusionError::internal(format!(
+                "Failed to create physical plan for streamed execution: {e}"
+            ))
+        })?;
+
+        execute_stream(physical_plan, state.task_ctx()).map_err(|e| {
+            VegaFusionError::internal(format!("Failed to start streamed plan execution: {e}"))
+        })
+    }
+}
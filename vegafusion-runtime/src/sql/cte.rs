@@ -0,0 +1,214 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::sql::unparser::dialect::CustomDialectBuilder;
+use datafusion::sql::unparser::Unparser;
+use datafusion_expr::{LogicalPlan, LogicalPlanBuilder, TableSource};
+use vegafusion_common::arrow::datatypes::Schema;
+use vegafusion_common::error::{Result, VegaFusionError};
+
+use super::SqlDialect;
+
+/// Renders `plan` as a single `WITH t0 AS (...), t1 AS (...) SELECT ... FROM tN` statement
+/// instead of DataFusion's default deeply-nested `SELECT ... FROM (SELECT ... FROM (...))`.
+///
+/// Every non-leaf `Projection`/`Filter`/`Aggregate` node gets its own generated CTE name (`t0`,
+/// `t1`, ...); a subplan reached through the same shared `Arc` from more than one place is only
+/// materialized once and referenced by name everywhere else. Structurally identical subplans held
+/// in separate allocations are not detected as duplicates and each get their own CTE. This both
+/// produces more readable
+/// SQL and sidesteps the fragile qualifier-stripping that nested subqueries otherwise require,
+/// since every CTE's projection list is unqualified to begin with.
+pub fn plan_to_cte_sql(plan: &LogicalPlan, dialect: &dyn SqlDialect) -> Result<String> {
+    let unparser_dialect = dialect
+        .configure_unparser_dialect(CustomDialectBuilder::new())
+        .build();
+    let unparser = Unparser::new(&unparser_dialect).with_pretty(true);
+
+    let mut builder = CteBuilder {
+        unparser: &unparser,
+        dialect,
+        seen: HashMap::new(),
+        ctes: Vec::new(),
+        next_id: 0,
+    };
+
+    let (top_plan, top_name) = builder.stage(Arc::new(plan.clone()))?;
+
+    let body = match top_name {
+        Some(name) => format!("SELECT * FROM {name}"),
+        None => {
+            // The top node wasn't itself staged into a CTE (a bare leaf relation, or a
+            // `Join`/`Sort`/`Limit`/... rebuilt over staged inputs), so unparse it directly; any
+            // CTEs its inputs were staged into still go in the `WITH` clause below.
+            let mut statement = unparser.plan_to_sql(&top_plan).map_err(|e| {
+                VegaFusionError::unparser(format!(
+                    "Failed to generate SQL AST from logical plan: {}",
+                    e
+                ))
+            })?;
+            super::apply_statement_rewrites(&unparser, &mut statement, dialect)?;
+            statement.to_string()
+        }
+    };
+
+    if builder.ctes.is_empty() {
+        return Ok(body);
+    }
+
+    let cte_clauses = builder
+        .ctes
+        .iter()
+        .map(|(name, body)| format!("{name} AS (\n{body}\n)"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    Ok(format!("WITH {cte_clauses}\n{body}"))
+}
+
+struct CteBuilder<'a> {
+    unparser: &'a Unparser<'a>,
+    dialect: &'a dyn SqlDialect,
+    /// Maps a shared subplan's pointer identity to the CTE name it was already materialized
+    /// under, so a subplan referenced from more than one place is only emitted once.
+    seen: HashMap<usize, String>,
+    /// Generated CTEs in dependency order (each only references earlier entries).
+    ctes: Vec<(String, String)>,
+    next_id: usize,
+}
+
+impl<'a> CteBuilder<'a> {
+    /// Recursively stages `plan`, hoisting every non-leaf `Projection`/`Filter`/`Aggregate` node
+    /// into its own CTE. Returns the (possibly rewritten, with its own input swapped for a CTE
+    /// reference) plan node, plus the CTE name it was materialized under, if any -- `None` means
+    /// `plan` itself was not materialized as a CTE (a leaf relation, or a `Join`/`Sort`/... node
+    /// rebuilt over its staged inputs).
+    fn stage(&mut self, plan: Arc<LogicalPlan>) -> Result<(LogicalPlan, Option<String>)> {
+        let ptr_key = Arc::as_ptr(&plan) as usize;
+        if let Some(name) = self.seen.get(&ptr_key) {
+            let scan = self.cte_reference(plan.schema().inner().clone(), name)?;
+            return Ok((scan, Some(name.clone())));
+        }
+
+        match plan.as_ref() {
+            LogicalPlan::Projection(_) | LogicalPlan::Filter(_) | LogicalPlan::Aggregate(_) => {
+                let input = single_child(&plan);
+                let (staged_input, _) = self.stage(input)?;
+
+                let rewritten = replace_single_child(&plan, staged_input)?;
+                let name = self.materialize(&rewritten)?;
+                self.seen.insert(ptr_key, name.clone());
+
+                let scan = self.cte_reference(rewritten.schema().inner().clone(), &name)?;
+                Ok((scan, Some(name)))
+            }
+            LogicalPlan::Join(join) => {
+                let (left, _) = self.stage(join.left.clone())?;
+                let (right, _) = self.stage(join.right.clone())?;
+                let mut new_join = join.clone();
+                new_join.left = Arc::new(left);
+                new_join.right = Arc::new(right);
+                Ok((LogicalPlan::Join(new_join), None))
+            }
+            // Every other node type (`Sort`, `Limit`, `Window`, `Distinct`, `SubqueryAlias`,
+            // `Union`, ...) isn't staged into its own CTE, but still has to be recursed through
+            // rather than left as-is -- otherwise a `Projection`/`Filter`/`Aggregate` nested
+            // underneath it (e.g. the `Window` node `with_index()` adds on top of a filtered
+            // projection) would never get staged at all, and would instead get unparsed as one
+            // raw nested-subquery tree with its original, possibly ambiguous, column references.
+            other => {
+                let inputs = other.inputs();
+                let staged_inputs = inputs
+                    .iter()
+                    .map(|input| {
+                        // `input` is staged through a fresh `Arc` that is freed again once
+                        // staged; drop its `seen` entry so an unrelated node later allocated at
+                        // the same address isn't mistaken for it.
+                        let input = Arc::new((*input).clone());
+                        let ptr_key = Arc::as_ptr(&input) as usize;
+                        let staged = self.stage(input).map(|(p, _)| p);
+                        self.seen.remove(&ptr_key);
+                        staged
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                if staged_inputs.is_empty() {
+                    // A leaf relation (table scan, values list, ...) -- nothing to recurse into.
+                    return Ok(((*plan).clone(), None));
+                }
+
+                let rebuilt = other.with_new_exprs(other.expressions(), staged_inputs)?;
+                Ok((rebuilt, None))
+            }
+        }
+    }
+
+    /// Unparses `plan` as a standalone query, applies this dialect's row-number/float-literal/AST
+    /// rewrites to it the same as the non-CTE path does, and records it as a new CTE, returning
+    /// its name.
+    fn materialize(&mut self, plan: &LogicalPlan) -> Result<String> {
+        let name = format!("t{}", self.next_id);
+        self.next_id += 1;
+
+        let mut statement = self.unparser.plan_to_sql(plan).map_err(|e| {
+            VegaFusionError::unparser(format!("Failed to unparse CTE body for {name}: {}", e))
+        })?;
+        super::apply_statement_rewrites(self.unparser, &mut statement, self.dialect)?;
+        self.ctes.push((name.clone(), statement.to_string()));
+        Ok(name)
+    }
+
+    /// Builds a schema-only placeholder scan of `name` so the outer stage's `Unparser` output
+    /// references the CTE by name (`FROM tN`) instead of re-embedding its body.
+    fn cte_reference(&self, schema: Arc<Schema>, name: &str) -> Result<LogicalPlan> {
+        let source = Arc::new(CteTableSource { schema });
+        LogicalPlanBuilder::scan(name, source, None)
+            .and_then(|b| b.build())
+            .map_err(|e| {
+                VegaFusionError::unparser(format!("Failed to build CTE reference scan: {}", e))
+            })
+    }
+}
+
+/// A schema-only `TableSource` standing in for an already-materialized CTE -- it carries no
+/// data, only the CTE's output schema, so the `Unparser` renders a plain `FROM <cte_name>`.
+#[derive(Debug)]
+struct CteTableSource {
+    schema: Arc<Schema>,
+}
+
+impl TableSource for CteTableSource {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn get_logical_plan(&self) -> Option<Cow<'_, LogicalPlan>> {
+        None
+    }
+}
+
+fn single_child(plan: &LogicalPlan) -> Arc<LogicalPlan> {
+    match plan {
+        LogicalPlan::Projection(p) => p.input.clone(),
+        LogicalPlan::Filter(f) => f.input.clone(),
+        LogicalPlan::Aggregate(a) => a.input.clone(),
+        other => panic!("single_child called on a plan node without exactly one input: {other:?}"),
+    }
+}
+
+/// Rebuilds `plan` with its single child replaced by `new_input`, rewriting any column
+/// references to it via [`super::qualify_or_strip_columns`]: unqualified where `new_input`'s
+/// schema has no colliding names, or re-qualified against generated per-relation aliases where it
+/// does (e.g. `new_input` is itself a join of two relations sharing a column name) -- the same
+/// ambiguity-aware rewrite the nested-subquery path uses, so hoisting a stage into its own CTE
+/// can never silently collapse two distinct columns into one.
+fn replace_single_child(plan: &LogicalPlan, new_input: LogicalPlan) -> Result<LogicalPlan> {
+    let (exprs, new_input) = super::qualify_or_strip_columns(&plan.expressions(), new_input)?;
+    plan.with_new_exprs(exprs, vec![new_input])
+}
@@ -0,0 +1,209 @@
+use super::spark_functions::remap_spark_function;
+use datafusion::sql::unparser::dialect::CustomDialectBuilder;
+use datafusion_common::Column;
+use datafusion_expr::{Expr, LogicalPlan};
+use sqlparser::ast::{self, visit_expressions_mut};
+use std::ops::ControlFlow;
+use vegafusion_common::error::Result;
+
+/// An additional `LogicalPlan` rewrite a dialect wants applied before unparsing, on top of the
+/// built-in passes `logical_plan_to_sql` already runs (qualifier stripping, IN-list
+/// reconstruction).
+pub type LogicalPlanRewrite = Box<dyn Fn(LogicalPlan) -> Result<LogicalPlan> + Send + Sync>;
+
+/// An additional AST rewrite a dialect wants applied after unparsing, on top of the
+/// row-number/float-literal fixups driven by `window_for_row_index`/`render_float_literal`.
+pub type AstRewrite = Box<dyn Fn(&mut ast::Statement) + Send + Sync>;
+
+/// Describes how a [`datafusion_expr::LogicalPlan`] should be rendered as SQL
+/// text for a particular warehouse/engine.
+///
+/// The default `logical_plan_to_spark_sql` path baked a handful of
+/// Spark-specific decisions directly into the unparsing code (how the
+/// `_vf_order` row index is produced, how non-finite float literals are
+/// spelled, how identifiers are quoted, and whether subquery projections may
+/// reference qualified columns). Pulling those decisions out into a trait
+/// lets a `PlanExecutor` render the same logical plan correctly for whatever
+/// engine it forwards queries to.
+pub trait SqlDialect: Send + Sync {
+    /// The expression used to order the `row_number()` window that backs
+    /// `with_index()`'s `_vf_order` column.
+    fn window_for_row_index(&self) -> Expr;
+
+    /// Render a non-finite float literal (`NaN`/`Infinity`/`-Infinity`) as
+    /// dialect-specific SQL text, or `None` to fall back to the Spark-style
+    /// `float('...')` wrapping.
+    fn render_float_literal(&self, f: f64) -> Option<String>;
+
+    /// Quote an identifier using this dialect's quoting convention.
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("\"{ident}\"")
+    }
+
+    /// Whether this dialect accepts qualified (`table.column`) identifiers in
+    /// a subquery's projection list. DataFusion's unparser emits such
+    /// qualified names for nested projections, which most engines reject
+    /// once the table is out of scope, so this defaults to `false`.
+    fn supports_qualified_subquery_columns(&self) -> bool {
+        false
+    }
+
+    /// Configure the underlying `datafusion::sql::unparser::dialect` used to render the AST.
+    /// The default returns `builder` unchanged, so identifiers are emitted unquoted;
+    /// `quote_identifier` is not consulted here. A dialect that needs its identifiers quoted, or
+    /// finer control (e.g. its own float/string literal formatting), can override this to
+    /// further configure `builder` before it's built.
+    fn configure_unparser_dialect(&self, builder: CustomDialectBuilder) -> CustomDialectBuilder {
+        builder
+    }
+
+    /// Additional logical-plan rewrite passes this dialect needs, beyond the built-in
+    /// qualifier-stripping and IN-list passes. Most dialects don't need any.
+    fn logical_plan_rewrites(&self) -> Vec<LogicalPlanRewrite> {
+        vec![]
+    }
+
+    /// Additional AST rewrite passes this dialect needs, beyond the row-number/float-literal
+    /// fixups driven by `window_for_row_index`/`render_float_literal`. This is where
+    /// dialect-specific quirks that don't fit the fixed hooks above (function remappings,
+    /// IN-list reconstruction, etc.) get plugged in.
+    fn ast_rewrites(&self) -> Vec<AstRewrite> {
+        vec![]
+    }
+
+    /// Whether multi-stage plans should be rendered as `WITH t0 AS (...), ... SELECT ... FROM
+    /// tN` instead of DataFusion's default nested `SELECT ... FROM (SELECT ...)` subqueries.
+    /// Defaults to `false` so existing callers' generated SQL doesn't change shape; warehouses
+    /// that prefer (or require) readable CTEs over deep nesting can override this.
+    fn prefers_cte_structuring(&self) -> bool {
+        false
+    }
+
+    /// Whether to run DataFusion's `SimplifyExpressions` optimizer rule over the plan before
+    /// unparsing, folding constant arithmetic, collapsing redundant casts, and normalizing
+    /// boolean predicates. Defaults to `true`; callers that want SQL mirroring the logical plan
+    /// 1:1 (e.g. for debugging) can override this to `false`.
+    fn simplify_before_unparse(&self) -> bool {
+        true
+    }
+
+    /// Whether to reconstruct `col IN (...)`/`col NOT IN (...)` predicates out of equivalent
+    /// `OR`-of-equality/`AND`-of-inequality chains before unparsing. Defaults to `true`, since
+    /// the more compact `IN` form is both valid SQL everywhere and more useful for downstream
+    /// partition/row-group pruning; a dialect producing SQL for 1:1 plan comparison can disable
+    /// it.
+    fn reconstructs_in_list_predicates(&self) -> bool {
+        true
+    }
+}
+
+/// Spark treats `monotonically_increasing_id()` as a pseudo-column usable
+/// directly inside an `ORDER BY`, which is what the original
+/// `logical_plan_to_spark_sql` hardcoded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SparkDialect;
+
+impl SqlDialect for SparkDialect {
+    fn window_for_row_index(&self) -> Expr {
+        Expr::Column(Column::from_name("monotonically_increasing_id()"))
+    }
+
+    fn render_float_literal(&self, _f: f64) -> Option<String> {
+        None
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("`{ident}`")
+    }
+
+    fn ast_rewrites(&self) -> Vec<AstRewrite> {
+        vec![Box::new(|statement: &mut ast::Statement| {
+            visit_expressions_mut(statement, |expr: &mut ast::Expr| {
+                remap_spark_function(expr);
+                ControlFlow::<()>::Continue(())
+            });
+        })]
+    }
+}
+
+/// Postgres and DuckDB have no monotonic-id pseudo-column, so row order for
+/// `with_index()` is left to whatever order the rows already arrive in; they
+/// also spell non-finite floats as casts rather than function calls.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostgresDialect;
+
+impl SqlDialect for PostgresDialect {
+    fn window_for_row_index(&self) -> Expr {
+        Expr::Literal(datafusion_common::ScalarValue::Null)
+    }
+
+    fn render_float_literal(&self, f: f64) -> Option<String> {
+        render_as_double_precision_cast(f)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DuckDbDialect;
+
+impl SqlDialect for DuckDbDialect {
+    fn window_for_row_index(&self) -> Expr {
+        Expr::Literal(datafusion_common::ScalarValue::Null)
+    }
+
+    fn render_float_literal(&self, f: f64) -> Option<String> {
+        render_as_double_precision_cast(f)
+    }
+}
+
+/// BigQuery has no monotonic-id function either, and spells non-finite
+/// floats using its own `FLOAT64` cast syntax.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BigQueryDialect;
+
+impl SqlDialect for BigQueryDialect {
+    fn window_for_row_index(&self) -> Expr {
+        Expr::Literal(datafusion_common::ScalarValue::Null)
+    }
+
+    fn render_float_literal(&self, f: f64) -> Option<String> {
+        let spelling = float_spelling(f)?;
+        Some(format!("CAST('{spelling}' AS FLOAT64)"))
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        format!("`{ident}`")
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SnowflakeDialect;
+
+impl SqlDialect for SnowflakeDialect {
+    fn window_for_row_index(&self) -> Expr {
+        Expr::Literal(datafusion_common::ScalarValue::Null)
+    }
+
+    fn render_float_literal(&self, f: f64) -> Option<String> {
+        let spelling = float_spelling(f)?;
+        Some(format!("'{spelling}'::FLOAT"))
+    }
+}
+
+fn render_as_double_precision_cast(f: f64) -> Option<String> {
+    let spelling = float_spelling(f)?;
+    Some(format!("'{spelling}'::double precision"))
+}
+
+/// Returns the canonical spelling (`NaN`, `Infinity`, `-Infinity`) used by
+/// most warehouses' string-cast literal syntax, or `None` for finite values.
+fn float_spelling(f: f64) -> Option<&'static str> {
+    if f.is_nan() {
+        Some("NaN")
+    } else if f == f64::INFINITY {
+        Some("Infinity")
+    } else if f == f64::NEG_INFINITY {
+        Some("-Infinity")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,534 @@
+mod cte;
+mod dialect;
+mod spark;
+mod spark_functions;
+
+pub use cte::plan_to_cte_sql;
+pub use dialect::{
+    AstRewrite, BigQueryDialect, DuckDbDialect, LogicalPlanRewrite, PostgresDialect,
+    SnowflakeDialect, SparkDialect, SqlDialect,
+};
+pub use spark::logical_plan_to_spark_sql;
+
+use datafusion::sql::unparser::dialect::CustomDialectBuilder;
+use datafusion::sql::unparser::Unparser;
+use datafusion_common::tree_node::{Transformed, TreeNode};
+use datafusion_common::Column;
+use datafusion_expr::expr::InList;
+use datafusion_expr::{BinaryExpr, Expr, LogicalPlan, Operator};
+use datafusion_optimizer::optimizer::OptimizerContext;
+use datafusion_optimizer::simplify_expressions::SimplifyExpressions;
+use datafusion_optimizer::OptimizerRule;
+use sqlparser::ast::{self, visit_expressions_mut};
+use std::ops::ControlFlow;
+use vegafusion_common::data::ORDER_COL;
+use vegafusion_common::error::{Result, VegaFusionError};
+
+/// Converts a DataFusion logical plan into SQL text for the given target
+/// [`SqlDialect`]. This is the dialect-generic entry point that
+/// `logical_plan_to_spark_sql` and friends are built on top of; a custom
+/// `PlanExecutor` that forwards plans to a warehouse should call this
+/// directly with the dialect matching that warehouse.
+pub fn logical_plan_to_sql(plan: &LogicalPlan, dialect: &dyn SqlDialect) -> Result<String> {
+    let plan = plan.clone();
+    let plan = if dialect.simplify_before_unparse() {
+        simplify_plan(plan)?
+    } else {
+        plan
+    };
+
+    // CTE structuring does its own qualifier handling per-stage (see `cte::replace_single_child`),
+    // so the nested-subquery qualifier rewrite below would be redundant -- and potentially
+    // conflicting -- work for dialects that opt into it.
+    let mut processed_plan = if dialect.supports_qualified_subquery_columns()
+        || dialect.prefers_cte_structuring()
+    {
+        plan
+    } else {
+        rewrite_subquery_column_identifiers(plan)?
+    };
+
+    if dialect.reconstructs_in_list_predicates() {
+        processed_plan = rewrite_in_list_predicates(processed_plan)?;
+    }
+
+    for rewrite in dialect.logical_plan_rewrites() {
+        processed_plan = rewrite(processed_plan)?;
+    }
+
+    if dialect.prefers_cte_structuring() {
+        // `plan_to_cte_sql` unparses each staged CTE body (and the top-level statement) itself,
+        // applying the same row-number/float-literal/dialect AST rewrites as the path below to
+        // every statement it produces -- so dialect-generic passes never get skipped just
+        // because a dialect also prefers CTE structuring.
+        return cte::plan_to_cte_sql(&processed_plan, dialect);
+    }
+
+    let unparser_dialect = dialect.configure_unparser_dialect(CustomDialectBuilder::new()).build();
+    let unparser = Unparser::new(&unparser_dialect).with_pretty(true);
+
+    let mut statement = unparser.plan_to_sql(&processed_plan).map_err(|e| {
+        VegaFusionError::unparser(format!("Failed to generate SQL AST from logical plan: {}", e))
+    })?;
+
+    apply_statement_rewrites(&unparser, &mut statement, dialect)?;
+
+    Ok(statement.to_string())
+}
+
+/// Applies the dialect-generic AST-level passes (row-number rewriting, non-finite float
+/// literals, then any dialect-specific `ast_rewrites`) to an already-unparsed `Statement`.
+/// Shared by the nested-subquery path above and `cte::plan_to_cte_sql`, so every SQL statement
+/// either path emits -- whether it's the sole top-level query or one CTE body among several --
+/// gets the same treatment.
+pub(super) fn apply_statement_rewrites(
+    unparser: &Unparser,
+    statement: &mut ast::Statement,
+    dialect: &dyn SqlDialect,
+) -> Result<()> {
+    rewrite_row_number(unparser, statement, dialect)?;
+    rewrite_inf_and_nan(statement, dialect);
+
+    for rewrite in dialect.ast_rewrites() {
+        rewrite(statement);
+    }
+
+    Ok(())
+}
+
+/// Runs DataFusion's `SimplifyExpressions` optimizer rule over `plan`, folding constant
+/// arithmetic, collapsing redundant casts, and normalizing boolean predicates before the
+/// `Unparser` ever sees the plan. This rule only rewrites expressions in place -- unlike the
+/// broader optimizer pipeline (projection/filter push-down, dead column elimination, ...), it
+/// never adds, removes, or reorders a plan's output columns, so the `ORDER_COL` ordering column
+/// that `TransformPipelineUtils::build_dataframe` relies on is always preserved.
+fn simplify_plan(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let had_order_col = plan.schema().field_with_unqualified_name(ORDER_COL).is_ok();
+
+    let rule = SimplifyExpressions::new();
+    let config = OptimizerContext::new();
+    let simplified = rule
+        .rewrite(plan, &config)
+        .map_err(|e| {
+            VegaFusionError::unparser(format!(
+                "Failed to simplify logical plan before unparsing: {}",
+                e
+            ))
+        })?
+        .data;
+
+    if had_order_col && simplified.schema().field_with_unqualified_name(ORDER_COL).is_err() {
+        return Err(VegaFusionError::internal(format!(
+            "Expression simplification must not drop the {ORDER_COL} ordering column"
+        )));
+    }
+
+    Ok(simplified)
+}
+
+/// `row_number()` is unparsed by DataFusion with a frame clause
+/// (`ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING`) that most
+/// warehouses reject for a plain row index. Replace it with `ORDER BY
+/// <dialect.window_for_row_index()>` and drop the frame.
+fn rewrite_row_number(
+    unparser: &Unparser,
+    statement: &mut ast::Statement,
+    dialect: &dyn SqlDialect,
+) -> Result<()> {
+    let order_by_expr = unparser.expr_to_sql(&dialect.window_for_row_index()).map_err(|e| {
+        VegaFusionError::unparser(format!("Failed to unparse row index ordering expression: {}", e))
+    })?;
+
+    visit_expressions_mut(statement, |expr: &mut ast::Expr| {
+        if let ast::Expr::Function(func) = expr {
+            if func.name.to_string().to_lowercase() == "row_number" {
+                if let Some(ast::WindowType::WindowSpec(ref mut window_spec)) = &mut func.over {
+                    window_spec.window_frame = None;
+                    window_spec.order_by = vec![ast::OrderByExpr {
+                        expr: order_by_expr.clone(),
+                        options: ast::OrderByOptions {
+                            asc: None,
+                            nulls_first: None,
+                        },
+                        with_fill: None,
+                    }];
+                }
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+
+    Ok(())
+}
+
+/// DataFusion renders NaN/Infinity as bare numeric literals; most engines
+/// require a dialect-specific spelling, which `dialect.render_float_literal`
+/// supplies. Dialects that return `None` get the Spark-style `float('...')`
+/// wrapping from `spark_style_float_wrapper`.
+fn rewrite_inf_and_nan(statement: &mut ast::Statement, dialect: &dyn SqlDialect) {
+    const SPECIAL_VALUES: &[&str] = &[
+        "nan", "inf", "infinity", "+inf", "+infinity", "-inf", "-infinity",
+    ];
+
+    visit_expressions_mut(statement, |expr: &mut ast::Expr| {
+        if let ast::Expr::Value(value) = expr {
+            if let ast::Value::Number(num_str, _) = &value.value {
+                if SPECIAL_VALUES.contains(&num_str.to_lowercase().as_str()) {
+                    let parsed: f64 = match num_str.to_lowercase().as_str() {
+                        "nan" => f64::NAN,
+                        "inf" | "infinity" | "+inf" | "+infinity" => f64::INFINITY,
+                        "-inf" | "-infinity" => f64::NEG_INFINITY,
+                        _ => f64::NAN,
+                    };
+                    if let Some(rendered) = dialect.render_float_literal(parsed) {
+                        *expr = ast::Expr::Identifier(ast::Ident::new(rendered));
+                    } else {
+                        *expr = spark_style_float_wrapper(num_str, value);
+                    }
+                }
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+}
+
+/// Fallback rendering used when a dialect returns `None` from
+/// `render_float_literal`, matching the original Spark-only `float('...')`
+/// wrapping so dialects that don't override it keep the prior behavior.
+fn spark_style_float_wrapper(num_str: &str, value: &ast::ValueWithSpan) -> ast::Expr {
+    ast::Expr::Function(ast::Function {
+        name: ast::ObjectName::from(vec![ast::Ident::new("float")]),
+        args: ast::FunctionArguments::List(ast::FunctionArgumentList {
+            duplicate_treatment: None,
+            args: vec![ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(
+                ast::Expr::Value(ast::ValueWithSpan {
+                    value: ast::Value::SingleQuotedString(num_str.to_string()),
+                    span: value.span.clone(),
+                }),
+            ))],
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+        uses_odbc_syntax: false,
+        parameters: ast::FunctionArguments::None,
+    })
+}
+
+/// DataFusion logical plan which uses compound names when selecting from subquery:
+/// ```
+/// SELECT orders.customer_name, orders.customer_age FROM (SELECT orders.customer_name, orders.customer_age FROM orders)
+/// ```
+/// This is not valid SQL, as `orders` isn't available once we get out of the first query. So we
+/// rewrite the logical plan to replace compound names with just the column names in projections
+/// that select data from another projection, a join, or a subquery alias.
+///
+/// Stripping qualifiers unconditionally is only safe when the input schema has no name
+/// collisions -- once a plan joins two relations that both expose a column with the same name
+/// (e.g. `customer_name` on both sides of a join), dropping the qualifier makes the reference
+/// ambiguous or silently picks the wrong side. In that case we instead assign each distinct
+/// relation a short, deduplicated alias (`t0`, `t1`, ...) and qualify with that alias rather than
+/// the original (possibly colliding) table name.
+fn rewrite_subquery_column_identifiers(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let processed_plan = plan
+        .transform_up_with_subqueries(|p| {
+            if let LogicalPlan::Projection(projection) = &p {
+                // only touch projections that read from another projection, a join, or an
+                // aliased subquery -- a plain table scan never needs requalifying.
+                if matches!(
+                    *projection.input,
+                    LogicalPlan::Projection { .. }
+                        | LogicalPlan::Join { .. }
+                        | LogicalPlan::SubqueryAlias { .. }
+                ) {
+                    let new_plan_node = qualify_subquery_projection(&p, projection)?;
+                    return Ok(Transformed::yes(new_plan_node));
+                }
+            }
+
+            Ok(Transformed::no(p))
+        })
+        .map_err(|e| {
+            VegaFusionError::unparser(format!(
+                "Failed to rewrite subquery column identifiers: {}",
+                e
+            ))
+        })?
+        .data;
+
+    Ok(processed_plan)
+}
+
+/// Rewrites `col = v1 OR col = v2 OR ...` into `col IN (v1, v2, ...)`, and `col != v1 AND
+/// col != v2 AND ...` into `col NOT IN (v1, v2, ...)`, applied to every `Filter` node's
+/// predicate. Modeled on DataFusion's literal-guarantee analysis: each disjunct/conjunct either
+/// constrains a column to a finite set of literal values (an "in" guarantee) or excludes one (a
+/// "not in" guarantee); reconstructing the minimal `IN`/`NOT IN` clause from that set produces
+/// more compact SQL that downstream engines can use for partition/row-group pruning, instead of
+/// an `OR`/`AND` chain they'd otherwise have to reason about conjunct-by-conjunct.
+fn rewrite_in_list_predicates(plan: LogicalPlan) -> Result<LogicalPlan> {
+    plan.transform_up(|p| {
+        if let LogicalPlan::Filter(mut filter) = p {
+            let rewritten = collapse_to_in_list(filter.predicate.clone());
+            if rewritten != filter.predicate {
+                filter.predicate = rewritten;
+                return Ok(Transformed::yes(LogicalPlan::Filter(filter)));
+            }
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+        Ok(Transformed::no(p))
+    })
+    .map_err(|e| {
+        VegaFusionError::unparser(format!("Failed to reconstruct IN-list predicates: {}", e))
+    })
+    .map(|t| t.data)
+}
+
+/// Tries both the "in" (`OR`-of-`=`) and "not in" (`AND`-of-`!=`) guarantee shapes against a
+/// single predicate expression, returning the reconstructed `IN`/`NOT IN` expression if either
+/// matches. Otherwise, if `expr` itself is an `AND` of several conjuncts -- e.g. `(category =
+/// 'A' OR category = 'B') AND date >= X` -- recurses into each conjunct independently, since a
+/// compound predicate like that never matches as a single homogeneous guarantee chain even
+/// though one of its conjuncts does. Returns `expr` unchanged if nothing was collapsible.
+fn collapse_to_in_list(expr: Expr) -> Expr {
+    if let Some((column, values)) = collect_guarantee(&expr, Operator::Or, Operator::Eq) {
+        return Expr::InList(InList::new(Box::new(column), values, false));
+    }
+    if let Some((column, values)) = collect_guarantee(&expr, Operator::And, Operator::NotEq) {
+        return Expr::InList(InList::new(Box::new(column), values, true));
+    }
+
+    if let Expr::BinaryExpr(BinaryExpr {
+        left,
+        op: Operator::And,
+        right,
+    }) = &expr
+    {
+        let new_left = collapse_to_in_list((**left).clone());
+        let new_right = collapse_to_in_list((**right).clone());
+        if new_left != **left || new_right != **right {
+            return Expr::BinaryExpr(BinaryExpr::new(
+                Box::new(new_left),
+                Operator::And,
+                Box::new(new_right),
+            ));
+        }
+    }
+
+    expr
+}
+
+/// Walks a tree of `combinator`-joined `leaf_op` comparisons, all against the same column,
+/// returning that column and the set of literal values compared against -- provided the whole
+/// tree matches this shape and has at least two leaves (a single comparison is already minimal).
+fn collect_guarantee(
+    expr: &Expr,
+    combinator: Operator,
+    leaf_op: Operator,
+) -> Option<(Expr, Vec<Expr>)> {
+    let mut column = None;
+    let mut values = Vec::new();
+    if collect_guarantee_leaves(expr, combinator, leaf_op, &mut column, &mut values) && values.len() >= 2 {
+        column.map(|c| (c, values))
+    } else {
+        None
+    }
+}
+
+fn collect_guarantee_leaves(
+    expr: &Expr,
+    combinator: Operator,
+    leaf_op: Operator,
+    column: &mut Option<Expr>,
+    values: &mut Vec<Expr>,
+) -> bool {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) if *op == combinator => {
+            collect_guarantee_leaves(left, combinator, leaf_op, column, values)
+                && collect_guarantee_leaves(right, combinator, leaf_op, column, values)
+        }
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) if *op == leaf_op => {
+            let (col_side, lit_side) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(_), Expr::Literal(_)) => (left.as_ref(), right.as_ref()),
+                (Expr::Literal(_), Expr::Column(_)) => (right.as_ref(), left.as_ref()),
+                _ => return false,
+            };
+            match column {
+                Some(existing) if existing != col_side => false,
+                _ => {
+                    *column = Some(col_side.clone());
+                    values.push(lit_side.clone());
+                    true
+                }
+            }
+        }
+        _ => false,
+    }
+}
+
+fn qualify_subquery_projection(
+    p: &LogicalPlan,
+    projection: &datafusion_expr::Projection,
+) -> Result<LogicalPlan> {
+    let (rewritten_exprs, new_input) =
+        qualify_or_strip_columns(&projection.expr, (*projection.input).clone())?;
+    p.with_new_exprs(rewritten_exprs, vec![new_input])
+}
+
+/// Rewrites `exprs` (a plan node's own expression list) to reference `input` directly instead of
+/// through a nested subquery/CTE: either stripping qualifiers down to bare column names (the
+/// common, safe case), or, when `input`'s schema has two or more fields sharing a bare name,
+/// wrapping each distinct relation under `input` in its own `SubqueryAlias` and qualifying with
+/// that generated alias instead -- so references stay unambiguous. Shared by the nested-subquery
+/// qualifier rewrite (`qualify_subquery_projection`) and the CTE stager
+/// (`cte::replace_single_child`), which both face the same problem once a plan node's child
+/// exposes two relations with an overlapping column name (e.g. `customer_name` on both sides of
+/// a join).
+pub(super) fn qualify_or_strip_columns(
+    exprs: &[Expr],
+    input: LogicalPlan,
+) -> Result<(Vec<Expr>, LogicalPlan)> {
+    let input_schema = input.schema().clone();
+
+    if has_ambiguous_columns(&input_schema) {
+        // Wrap each distinct relation under the input in its own `SubqueryAlias` so every
+        // column can be unambiguously qualified, then rewrite the projected expressions to
+        // reference those generated aliases instead of the original (colliding) names.
+        let aliases = relation_aliases(&input_schema);
+        let aliased_input = alias_relations(input, &aliases)?;
+        let rewritten_exprs = exprs
+            .iter()
+            .map(|e| {
+                e.clone()
+                    .transform_up(|mut ex| {
+                        if let Expr::Column(c) = &mut ex {
+                            if let Some(relation) = &c.relation {
+                                if let Some(alias) = aliases.get(relation) {
+                                    *c = Column::new(
+                                        Some(datafusion_common::TableReference::bare(
+                                            alias.clone(),
+                                        )),
+                                        c.name.clone(),
+                                    );
+                                    return Ok(Transformed::yes(ex));
+                                }
+                            }
+                            Ok(Transformed::no(ex))
+                        } else {
+                            Ok(Transformed::no(ex))
+                        }
+                    })
+                    .map(|t| t.data)
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok((rewritten_exprs, aliased_input))
+    } else {
+        let rewritten_exprs = exprs
+            .iter()
+            .map(|e| {
+                e.clone()
+                    .transform_up(|mut ex| {
+                        if let Expr::Column(c) = &mut ex {
+                            *c = Column::from_name(c.name.clone());
+                            Ok(Transformed::yes(ex))
+                        } else {
+                            Ok(Transformed::no(ex))
+                        }
+                    })
+                    .map(|t| t.data)
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok((rewritten_exprs, input))
+    }
+}
+
+/// Assigns each distinct relation qualifier appearing in `schema` a short, deduplicated alias
+/// (`t0`, `t1`, ...), in order of first appearance.
+fn relation_aliases(
+    schema: &datafusion_common::DFSchema,
+) -> std::collections::HashMap<datafusion_common::TableReference, String> {
+    let mut aliases = std::collections::HashMap::new();
+    for (qualifier, _) in schema.iter() {
+        if let Some(qualifier) = qualifier {
+            let next_id = aliases.len();
+            aliases
+                .entry(qualifier.clone())
+                .or_insert_with(|| format!("t{next_id}"));
+        }
+    }
+    aliases
+}
+
+/// Whether `schema` contains two or more fields sharing the same unqualified name -- the
+/// situation that makes stripping qualifiers ambiguous.
+fn has_ambiguous_columns(schema: &datafusion_common::DFSchema) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    for (_, field) in schema.iter() {
+        if !seen.insert(field.name()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Recursively wraps each distinct relation under `plan` in a `SubqueryAlias` using the
+/// generated alias from `aliases`, so the FROM/JOIN clause the unparser emits lines up with the
+/// qualifiers used when referencing those columns.
+fn alias_relations(
+    plan: LogicalPlan,
+    aliases: &std::collections::HashMap<datafusion_common::TableReference, String>,
+) -> Result<LogicalPlan> {
+    match plan {
+        LogicalPlan::Join(mut join) => {
+            let left = alias_relations((*join.left).clone(), aliases)?;
+            let right = alias_relations((*join.right).clone(), aliases)?;
+            join.left = std::sync::Arc::new(left);
+            join.right = std::sync::Arc::new(right);
+            Ok(LogicalPlan::Join(join))
+        }
+        // A `Filter`/`Projection` sitting between a `Join` and its leaf relations (a residual
+        // join predicate, or a pass-through projection) still has more than one relation
+        // underneath it, so it needs the same recursive treatment as `Join` rather than falling
+        // into the single-relation case below and getting collapsed under one alias.
+        LogicalPlan::Filter(mut filter) => {
+            let input = alias_relations((*filter.input).clone(), aliases)?;
+            filter.input = std::sync::Arc::new(input);
+            Ok(LogicalPlan::Filter(filter))
+        }
+        LogicalPlan::Projection(mut projection) => {
+            let input = alias_relations((*projection.input).clone(), aliases)?;
+            projection.input = std::sync::Arc::new(input);
+            Ok(LogicalPlan::Projection(projection))
+        }
+        plan => {
+            let qualifiers: std::collections::HashSet<_> =
+                plan.schema().iter().filter_map(|(q, _)| q.cloned()).collect();
+            if qualifiers.len() > 1 {
+                // A multi-relation subtree we don't know how to recurse through -- aliasing the
+                // whole thing under a single qualifier would silently collapse distinct
+                // relations onto one alias, so refuse instead of emitting ambiguous SQL.
+                return Err(VegaFusionError::unparser(
+                    "Cannot alias a multi-relation subtree for SQL generation: only Join/Filter/Projection nodes are recursed through between a Join and its leaf relations".to_string(),
+                ));
+            }
+
+            if let Some(qualifier) = qualifiers.into_iter().next() {
+                if let Some(alias) = aliases.get(&qualifier) {
+                    let aliased =
+                        datafusion_expr::SubqueryAlias::try_new(std::sync::Arc::new(plan), alias.clone())
+                            .map_err(|e| {
+                                VegaFusionError::unparser(format!(
+                                    "Failed to alias relation for SQL generation: {}",
+                                    e
+                                ))
+                            })?;
+                    return Ok(LogicalPlan::SubqueryAlias(aliased));
+                }
+            }
+
+            Ok(plan)
+        }
+    }
+}
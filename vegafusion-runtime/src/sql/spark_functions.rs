@@ -0,0 +1,81 @@
+use sqlparser::ast;
+
+/// DataFusion-emitted function names that Spark spells differently but otherwise calls the same
+/// way (same argument order/arity) -- a straight rename is enough.
+///
+/// `date_trunc(precision, timestamp)` and the `lpad`/`rpad` string-padding functions are
+/// deliberately absent from this table: DataFusion and Spark already agree on both the name and
+/// the argument order/arity for all three, so no rewrite is needed for them to run on Spark --
+/// see `test_logical_plan_to_spark_sql_passes_through_date_trunc_and_padding_unchanged` in
+/// `test_spark_sql.rs`.
+const SIMPLE_RENAMES: &[(&str, &str)] = &[
+    ("array_agg", "collect_list"),
+    ("character_length", "length"),
+    ("strpos", "instr"),
+    ("to_timestamp_seconds", "timestamp_seconds"),
+    ("to_timestamp_millis", "timestamp_millis"),
+    ("to_timestamp_micros", "timestamp_micros"),
+];
+
+/// Rewrites a single AST expression node to its Spark equivalent in place, if one of this
+/// module's remapping rules applies; a no-op for everything else. Threaded through
+/// `visit_expressions_mut` as part of `SparkDialect::ast_rewrites`, alongside the fixed
+/// `rewrite_row_number`/`rewrite_inf_and_nan` passes that already run for every dialect.
+///
+/// Many Vega transforms compile to date/time and string functions that DataFusion's unparser
+/// renders in forms Spark doesn't accept (`EXTRACT(... FROM ...)`, `||` concatenation, the
+/// `to_timestamp_*` family, ...); without this remapping the generated SQL fails at runtime even
+/// though the underlying plan is structurally valid.
+pub fn remap_spark_function(expr: &mut ast::Expr) {
+    match expr {
+        ast::Expr::Function(func) => remap_function_name(func),
+        ast::Expr::BinaryOp {
+            left,
+            op: ast::BinaryOperator::StringConcat,
+            right,
+        } => {
+            *expr = function_call("concat", vec![(**left).clone(), (**right).clone()]);
+        }
+        ast::Expr::Extract {
+            field,
+            expr: target,
+            ..
+        } => {
+            let unit = ast::Expr::Value(ast::ValueWithSpan {
+                value: ast::Value::SingleQuotedString(field.to_string().to_lowercase()),
+                span: sqlparser::tokenizer::Span::empty(),
+            });
+            *expr = function_call("date_part", vec![unit, (**target).clone()]);
+        }
+        _ => {}
+    }
+}
+
+/// Renames `func` in place if it matches an entry in [`SIMPLE_RENAMES`].
+fn remap_function_name(func: &mut ast::Function) {
+    let name = func.name.to_string().to_lowercase();
+    if let Some((_, spark_name)) = SIMPLE_RENAMES.iter().find(|(df_name, _)| *df_name == name) {
+        func.name = ast::ObjectName::from(vec![ast::Ident::new(*spark_name)]);
+    }
+}
+
+/// Builds an unqualified `name(args...)` function call expression.
+fn function_call(name: &str, args: Vec<ast::Expr>) -> ast::Expr {
+    ast::Expr::Function(ast::Function {
+        name: ast::ObjectName::from(vec![ast::Ident::new(name)]),
+        args: ast::FunctionArguments::List(ast::FunctionArgumentList {
+            duplicate_treatment: None,
+            args: args
+                .into_iter()
+                .map(|arg| ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(arg)))
+                .collect(),
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+        uses_odbc_syntax: false,
+        parameters: ast::FunctionArguments::None,
+    })
+}
@@ -1,3 +1,4 @@
+use crate::connection::{plan_to_remote_result, scan_remote_table, RemoteConnection};
 use crate::task_graph::timezone::RuntimeTzConfig;
 use async_trait::async_trait;
 use datafusion::prelude::SessionContext;
@@ -19,7 +20,7 @@ pub trait TaskCall {
         inline_datasets: HashMap<String, VegaFusionDataset>,
         ctx: Arc<SessionContext>,
     ) -> Result<(TaskValue, Vec<TaskValue>)>;
-    
+
     async fn plan(
         &self,
         values: &[TaskValue],
@@ -27,6 +28,25 @@ pub trait TaskCall {
         inline_datasets: HashMap<String, VegaFusionDataset>,
         ctx: Arc<SessionContext>,
     ) -> Result<(TaskPlan, Vec<TaskValue>)>;
+
+    /// Like `eval`, but given a `connection` to a remote SQL engine, may push the task's work
+    /// down to it via generated SQL (see `crate::connection::plan_to_remote_result`) instead of
+    /// materializing through the local `ctx`. The default ignores `connection` and falls back to
+    /// the local `eval`, which is always correct, just not pushed down. `Task::eval_remote`'s own
+    /// `TaskKind::DataSource` arm is the one case with an unambiguous pushdown target (see the
+    /// comment on that arm); a task kind whose own `TaskCall` impl knows how to resolve its data against
+    /// `connection` can likewise override this method directly.
+    async fn eval_remote(
+        &self,
+        values: &[TaskValue],
+        tz_config: &Option<RuntimeTzConfig>,
+        inline_datasets: HashMap<String, VegaFusionDataset>,
+        ctx: Arc<SessionContext>,
+        connection: Option<Arc<dyn RemoteConnection>>,
+    ) -> Result<(TaskValue, Vec<TaskValue>)> {
+        let _ = connection;
+        self.eval(values, tz_config, inline_datasets, ctx).await
+    }
 }
 
 #[async_trait]
@@ -73,4 +93,47 @@ impl TaskCall for Task {
             TaskKind::Signal(task) => task.plan(values, tz_config, inline_datasets, ctx).await,
         }
     }
+
+    async fn eval_remote(
+        &self,
+        values: &[TaskValue],
+        tz_config: &Option<RuntimeTzConfig>,
+        inline_datasets: HashMap<String, VegaFusionDataset>,
+        ctx: Arc<SessionContext>,
+        connection: Option<Arc<dyn RemoteConnection>>,
+    ) -> Result<(TaskValue, Vec<TaskValue>)> {
+        match self.task_kind() {
+            TaskKind::Value(value) => Ok((value.try_into()?, Default::default())),
+            // A `DataSourceTask` names an already-materialized dataset rather than producing new
+            // data itself, so it's the one task kind with an unambiguous remote pushdown target:
+            // if the connection's engine already has a table under that same name, scan and
+            // return it directly instead of resolving the dependency locally. `DataUrlTask`
+            // (fetches from a URL) and `DataValuesTask` (carries inline values) name no such
+            // remote-resident table -- pushing those down would first require uploading their
+            // data to the remote engine, which is outside what `RemoteConnection` exposes today
+            // -- so they keep the default local-`eval` fallback.
+            TaskKind::DataSource(task) => {
+                if let Some(connection) = &connection {
+                    if let Ok(remote_scan) = scan_remote_table(connection.as_ref(), &task.source).await {
+                        let table = plan_to_remote_result(connection.as_ref(), &remote_scan).await?;
+                        return Ok((TaskValue::Table(table), Default::default()));
+                    }
+                }
+                task.eval_remote(values, tz_config, inline_datasets, ctx, connection)
+                    .await
+            }
+            TaskKind::DataUrl(task) => {
+                task.eval_remote(values, tz_config, inline_datasets, ctx, connection)
+                    .await
+            }
+            TaskKind::DataValues(task) => {
+                task.eval_remote(values, tz_config, inline_datasets, ctx, connection)
+                    .await
+            }
+            TaskKind::Signal(task) => {
+                task.eval_remote(values, tz_config, inline_datasets, ctx, connection)
+                    .await
+            }
+        }
+    }
 }
@@ -0,0 +1,27 @@
+use datafusion::functions_window::expr_fn::rank;
+use datafusion_common::tree_node::TreeNode;
+use datafusion_expr::{col, ExprFunctionExt};
+use vegafusion_runtime::data::util::PureAggRewriter;
+
+#[test]
+fn test_pure_agg_rewriter_lifts_window_function_partition_and_order_by() {
+    // rank() OVER (PARTITION BY a ORDER BY b)
+    let window_expr = rank()
+        .partition_by(vec![col("a")])
+        .order_by(vec![col("b").sort(true, false)])
+        .build()
+        .unwrap();
+
+    let mut rewriter = PureAggRewriter::new();
+    let rewritten = window_expr.rewrite(&mut rewriter).unwrap().data;
+
+    // The window function itself is replaced with a reference to the generated column.
+    assert_eq!(rewritten, col("_win_0"));
+
+    assert!(rewriter.pure_aggs.is_empty());
+    assert_eq!(rewriter.pure_windows.len(), 1);
+
+    let lifted = &rewriter.pure_windows[0];
+    assert_eq!(lifted.partition_by, vec![col("a")]);
+    assert_eq!(lifted.order_by, vec![col("b")]);
+}
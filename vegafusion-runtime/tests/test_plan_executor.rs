@@ -11,71 +11,30 @@ use vegafusion_core::spec::chart::ChartSpec;
 use vegafusion_runtime::plan_executor::DataFusionPlanExecutor;
 use vegafusion_runtime::task_graph::runtime::VegaFusionRuntime;
 use vegafusion_runtime::datafusion::context::make_datafusion_context;
-use datafusion::datasource::{provider_as_source, MemTable};
-use datafusion_expr::LogicalPlanBuilder;
+use datafusion::datasource::MemTable;
 use vegafusion_common::arrow::datatypes::{DataType, Field, Schema};
 use vegafusion_common::arrow::array::{
     StringArray, Int64Array, Float64Array, RecordBatch,
 };
-use datafusion_common::tree_node::{Transformed, TreeNode, TreeNodeRewriter};
-use datafusion_expr::{LogicalPlan as DFLogicalPlan, Expr, TableSource};
 use datafusion::catalog::TableProvider;
-use std::any::Any;
-use std::borrow::Cow;
-
-#[derive(Debug, Clone)]
-struct SchemaOnlyTableSource {
-    schema: Arc<Schema>,
-}
-
-impl SchemaOnlyTableSource {
-    fn new(schema: Arc<Schema>) -> Self {
-        Self { schema }
-    }
-}
-
-impl TableSource for SchemaOnlyTableSource {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
-    fn schema(&self) -> Arc<Schema> {
-        self.schema.clone()
-    }
-
-    fn supports_filters_pushdown(
-        &self,
-        _filters: &[&Expr],
-    ) -> datafusion_common::Result<Vec<datafusion_expr::TableProviderFilterPushDown>> {
-        Ok(vec![])
-    }
-
-    fn get_logical_plan(&self) -> Option<Cow<'_, DFLogicalPlan>> {
-        None
-    }
-}
+use datafusion::physical_plan::SendableRecordBatchStream;
 
 #[derive(Clone)]
 struct TrackingPlanExecutor {
     call_count: Arc<AtomicUsize>,
+    stream_call_count: Arc<AtomicUsize>,
     plans_received: Arc<Mutex<Vec<LogicalPlan>>>,
-    movies_table: Arc<dyn TableProvider>,
     fallback_executor: Arc<DataFusionPlanExecutor>,
 }
 
 impl TrackingPlanExecutor {
     fn new() -> Self {
         let ctx = Arc::new(make_datafusion_context());
-        
-        let movies_table = create_movies_table();
-        let schema = movies_table.schema.clone();
-        let batches = movies_table.batches.clone();
-        let mem_table = Arc::new(MemTable::try_new(schema, vec![batches]).unwrap()) as Arc<dyn TableProvider>;
-        
+
         Self {
             call_count: Arc::new(AtomicUsize::new(0)),
+            stream_call_count: Arc::new(AtomicUsize::new(0)),
             plans_received: Arc::new(Mutex::new(Vec::new())),
-            movies_table: mem_table,
             fallback_executor: Arc::new(DataFusionPlanExecutor::new(ctx)),
         }
     }
@@ -84,33 +43,12 @@ impl TrackingPlanExecutor {
         self.call_count.load(Ordering::SeqCst)
     }
 
-    fn get_plans_received(&self) -> Vec<LogicalPlan> {
-        self.plans_received.lock().unwrap().clone()
+    fn get_stream_call_count(&self) -> usize {
+        self.stream_call_count.load(Ordering::SeqCst)
     }
-}
 
-struct TableRewriter {
-    movies_table: Arc<dyn TableProvider>,
-}
-
-impl TreeNodeRewriter for TableRewriter {
-    type Node = DFLogicalPlan;
-
-    fn f_up(&mut self, node: Self::Node) -> datafusion_common::Result<Transformed<Self::Node>> {
-        if let DFLogicalPlan::TableScan(scan) = &node {
-            if scan.table_name.table() == "movies" {
-                let new_scan = DFLogicalPlan::TableScan(datafusion_expr::TableScan {
-                    table_name: scan.table_name.clone(),
-                    source: provider_as_source(self.movies_table.clone()),
-                    projection: scan.projection.clone(),
-                    projected_schema: scan.projected_schema.clone(),
-                    filters: scan.filters.clone(),
-                    fetch: scan.fetch,
-                });
-                return Ok(Transformed::yes(new_scan));
-            }
-        }
-        Ok(Transformed::no(node))
+    fn get_plans_received(&self) -> Vec<LogicalPlan> {
+        self.plans_received.lock().unwrap().clone()
     }
 }
 
@@ -118,13 +56,25 @@ impl TreeNodeRewriter for TableRewriter {
 impl PlanExecutor for TrackingPlanExecutor {
     async fn execute_plan(&self, plan: LogicalPlan) -> Result<VegaFusionTable> {
         self.call_count.fetch_add(1, Ordering::SeqCst);
-        
+
+        self.plans_received.lock().unwrap().push(plan.clone());
+
+        // `movies` was registered via `VegaFusionDataset::from_table_provider`,
+        // so the plan we receive already scans the real `TableProvider` --
+        // no need to rewrite the `TableScan` to swap in a `MemTable` by hand.
+        self.fallback_executor.execute_plan(plan).await
+    }
+
+    // `pre_transform_extract` drives this instead of `execute_plan` so a
+    // large chart's rows can be counted against `extract_threshold` and
+    // spilled to the extracted dataset as they arrive, rather than forcing
+    // the whole transformed dataset into memory first.
+    async fn execute_plan_stream(&self, plan: LogicalPlan) -> Result<SendableRecordBatchStream> {
+        self.stream_call_count.fetch_add(1, Ordering::SeqCst);
+
         self.plans_received.lock().unwrap().push(plan.clone());
-        
-        let mut rewriter = TableRewriter { movies_table: self.movies_table.clone() };
-        let rewritten_plan = plan.rewrite(&mut rewriter).unwrap().data;
-        
-        self.fallback_executor.execute_plan(rewritten_plan).await
+
+        self.fallback_executor.execute_plan_stream(plan).await
     }
 }
 
@@ -192,10 +142,49 @@ async fn test_custom_executor_called_in_pre_transform_extract() {
         .unwrap();
     
     assert!(warnings.is_empty());
-    
-    let call_count = executor_clone.get_call_count();
-    println!("Custom executor was called {} times", call_count);
-    assert!(call_count > 0, "Custom executor should have been called at least once");
+
+    // `pre_transform_extract` consumes the plan incrementally via
+    // `execute_plan_stream` so it can count rows against `extract_threshold`
+    // as they arrive, rather than materializing the whole dataset up front.
+    let stream_call_count = executor_clone.get_stream_call_count();
+    println!("Custom executor stream was called {} times", stream_call_count);
+    assert!(stream_call_count > 0, "Custom executor stream should have been called at least once");
+}
+
+#[tokio::test]
+async fn test_custom_executor_stream_driven_by_low_extract_threshold() {
+    let tracking_executor = TrackingPlanExecutor::new();
+    let executor_clone = tracking_executor.clone();
+
+    let runtime = VegaFusionRuntime::new(None, Some(Arc::new(tracking_executor)));
+
+    let spec = get_simple_spec();
+    let inline_datasets = get_inline_datasets();
+
+    // NOTE: a tiny threshold is only exercised here to get `pre_transform_extract` to call
+    // `execute_plan_stream` at all; it does NOT exercise row-counting/spill-to-extracted-dataset
+    // behavior. That counting and the actual spill live in `VegaFusionRuntime`'s
+    // `pre_transform_extract` implementation (`task_graph::runtime`), which this tree does not
+    // contain, so this test cannot assert on `datasets` being non-empty.
+    let (_transformed_spec, _datasets, warnings) = runtime
+        .pre_transform_extract(
+            &spec,
+            &inline_datasets,
+            &vegafusion_core::proto::gen::pretransform::PreTransformExtractOpts {
+                preserve_interactivity: false,
+                local_tz: "UTC".to_string(),
+                default_input_tz: None,
+                extract_threshold: 1,
+                keep_variables: vec![],
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(warnings.is_empty());
+
+    let stream_call_count = executor_clone.get_stream_call_count();
+    assert!(stream_call_count > 0, "Custom executor stream should have been called at least once");
 }
 
 #[tokio::test]
@@ -407,21 +396,19 @@ fn create_movies_table() -> VegaFusionTable {
     VegaFusionTable::from(batch)
 }
 
-fn create_movies_logical_plan() -> LogicalPlan {
-    let schema = get_movies_schema();
-    
-    let table_source = Arc::new(SchemaOnlyTableSource::new(schema));
-    
-    LogicalPlanBuilder::scan("movies", table_source, None)
-        .unwrap()
-        .build()
-        .unwrap()
-}
-
 fn get_inline_datasets() -> std::collections::HashMap<String, VegaFusionDataset> {
-    let logical_plan = create_movies_logical_plan();
-    let dataset = VegaFusionDataset::from_plan(logical_plan);
-    
+    let movies_table = create_movies_table();
+    let schema = movies_table.schema.clone();
+    let batches = movies_table.batches.clone();
+    let mem_table: Arc<dyn TableProvider> =
+        Arc::new(MemTable::try_new(schema, vec![batches]).unwrap());
+
+    // `table://movies` is registered directly against the `TableProvider`
+    // rather than a pre-materialized `VegaFusionTable` or a pre-built
+    // `LogicalPlan`, so the runtime builds the `TableScan` against the real
+    // provider and drives its (possibly async, I/O-backed) `scan()` itself.
+    let dataset = VegaFusionDataset::from_table_provider(mem_table);
+
     let mut datasets = std::collections::HashMap::new();
     datasets.insert("movies".to_string(), dataset);
     datasets
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use vegafusion_common::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use vegafusion_common::data::table::VegaFusionTable;
+use vegafusion_common::error::Result;
+use vegafusion_runtime::connection::{plan_to_remote_result, scan_remote_table, RemoteConnection};
+use vegafusion_runtime::sql::{PostgresDialect, SqlDialect};
+
+/// A `RemoteConnection` that resolves a single fixed schema and records the SQL it was asked to
+/// execute, without actually running anything -- enough to exercise the schema-resolution ->
+/// unparse -> execute_sql pushdown path end to end.
+struct MockRemoteConnection {
+    schema: SchemaRef,
+    received_sql: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl RemoteConnection for MockRemoteConnection {
+    fn dialect(&self) -> &dyn SqlDialect {
+        &PostgresDialect
+    }
+
+    async fn resolve_schema(&self, _table_name: &str) -> Result<SchemaRef> {
+        Ok(self.schema.clone())
+    }
+
+    async fn execute_sql(&self, sql: &str) -> Result<VegaFusionTable> {
+        self.received_sql.lock().unwrap().push(sql.to_string());
+        VegaFusionTable::try_new(self.schema.clone(), vec![])
+    }
+}
+
+#[tokio::test]
+async fn test_plan_to_remote_result_pushes_down_generated_sql() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("customer_name", DataType::Utf8, false),
+        Field::new("customer_age", DataType::Int32, false),
+    ]));
+
+    let connection = MockRemoteConnection {
+        schema,
+        received_sql: Mutex::new(Vec::new()),
+    };
+
+    let plan = scan_remote_table(&connection, "customers").await?;
+    let _result = plan_to_remote_result(&connection, &plan).await?;
+
+    let received_sql = connection.received_sql.lock().unwrap();
+    assert_eq!(received_sql.len(), 1, "Expected exactly one SQL statement to be pushed down");
+    assert!(
+        received_sql[0].contains("FROM customers"),
+        "Pushed-down SQL should scan the remote table directly: {}",
+        received_sql[0]
+    );
+
+    Ok(())
+}
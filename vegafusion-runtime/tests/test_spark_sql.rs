@@ -1,6 +1,6 @@
 use datafusion::datasource::{provider_as_source, MemTable};
 use datafusion::prelude::{DataFrame, SessionContext};
-use datafusion_expr::{col, lit, LogicalPlanBuilder};
+use datafusion_expr::{col, lit, BinaryExpr, Expr, LogicalPlanBuilder, Operator};
 use std::sync::Arc;
 use vegafusion_common::arrow::array::RecordBatch;
 use vegafusion_common::arrow::datatypes::{DataType, Field, Schema};
@@ -106,7 +106,7 @@ async fn test_logical_plan_to_spark_sql_rewrites_subquery_column_identifiers() -
     let spark_sql = logical_plan_to_spark_sql(&plan)?;
 
     let expected_sql = "SELECT customer_name, customer_age FROM (SELECT test_table.customer_name, test_table.customer_age FROM test_table)";
-    
+
     assert_eq!(
         spark_sql.trim(),
         expected_sql,
@@ -116,3 +116,146 @@ async fn test_logical_plan_to_spark_sql_rewrites_subquery_column_identifiers() -
     Ok(())
 }
 
+#[tokio::test]
+async fn test_logical_plan_to_spark_sql_qualifies_ambiguous_join_columns() -> Result<(), Box<dyn std::error::Error>> {
+    let customers_fields = vec![
+        Field::new("customer_id", DataType::Int32, false),
+        Field::new("customer_name", DataType::Utf8, false),
+    ];
+    let orders_fields = vec![
+        Field::new("customer_id", DataType::Int32, false),
+        Field::new("customer_name", DataType::Utf8, false),
+    ];
+
+    let customers_df = create_test_dataframe(customers_fields).await?;
+    let orders_df = create_named_dataframe("orders_table", orders_fields).await?;
+
+    let joined_df = customers_df.join(
+        orders_df,
+        datafusion::logical_expr::JoinType::Inner,
+        &["customer_id"],
+        &["customer_id"],
+        None,
+    )?;
+
+    // Project both sides' `customer_name` columns through a wrapping projection, the same
+    // shape that previously triggered the unconditional qualifier-stripping rewrite.
+    let selected_df = joined_df.select(vec![
+        col("test_table.customer_name"),
+        col("orders_table.customer_name"),
+    ])?;
+
+    let plan = selected_df.logical_plan().clone();
+    let spark_sql = logical_plan_to_spark_sql(&plan)?;
+
+    // The two `customer_name` columns are ambiguous once unqualified, so the generator must
+    // keep them qualified with deduplicated, generated aliases instead of silently producing
+    // two identical (and wrong) bare `customer_name` references.
+    assert!(
+        spark_sql.contains("t0.customer_name") && spark_sql.contains("t1.customer_name"),
+        "Generated SQL should keep join columns qualified when they would otherwise collide: {spark_sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_spark_sql_passes_through_date_trunc_and_padding_unchanged(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("ts", DataType::Date32, false),
+        Field::new("name", DataType::Utf8, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let projected_df = df.select(vec![
+        datafusion_expr::expr_fn::date_trunc(lit("month"), col("ts")).alias("month"),
+        datafusion_expr::expr_fn::lpad(vec![col("name"), lit(10)]).alias("padded"),
+    ])?;
+
+    let plan = projected_df.logical_plan().clone();
+    let spark_sql = logical_plan_to_spark_sql(&plan)?;
+
+    assert!(
+        spark_sql.contains("date_trunc('month', test_table.ts)"),
+        "date_trunc already matches Spark's name/argument order, so it should pass through \
+         unchanged: {spark_sql}"
+    );
+    assert!(
+        spark_sql.contains("lpad(test_table.name, 10)"),
+        "lpad already matches Spark's name/argument order, so it should pass through \
+         unchanged: {spark_sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_spark_sql_remaps_array_agg() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let aggregated_df = df.aggregate(
+        vec![],
+        vec![datafusion_expr::expr_fn::array_agg(col("name")).alias("names")],
+    )?;
+
+    let plan = aggregated_df.logical_plan().clone();
+    let spark_sql = logical_plan_to_spark_sql(&plan)?;
+
+    assert!(
+        spark_sql.contains("collect_list"),
+        "Spark dialect should remap array_agg to collect_list: {spark_sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_spark_sql_remaps_string_concat_operator() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![Field::new("name", DataType::Utf8, false)];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let concat_expr = Expr::BinaryExpr(BinaryExpr::new(
+        Box::new(col("name")),
+        Operator::StringConcat,
+        Box::new(lit("!")),
+    ))
+    .alias("shouted");
+
+    let projected_df = df.select(vec![concat_expr])?;
+    let plan = projected_df.logical_plan().clone();
+    let spark_sql = logical_plan_to_spark_sql(&plan)?;
+
+    assert!(
+        spark_sql.contains("concat(test_table.name, '!')"),
+        "Spark dialect should rewrite || concatenation into a concat() call: {spark_sql}"
+    );
+
+    Ok(())
+}
+
+async fn create_named_dataframe(
+    table_name: &str,
+    schema_fields: Vec<Field>,
+) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    let ctx = SessionContext::new();
+
+    let schema = Arc::new(Schema::new(schema_fields));
+
+    let empty_batch = RecordBatch::new_empty(schema.clone());
+    let mem_table = MemTable::try_new(schema.clone(), vec![vec![empty_batch]])?;
+
+    let base_plan = LogicalPlanBuilder::scan(
+        table_name,
+        provider_as_source(Arc::new(mem_table)),
+        None,
+    )?
+    .build()?;
+
+    Ok(DataFrame::new(ctx.state(), base_plan))
+}
+
@@ -0,0 +1,310 @@
+use datafusion::datasource::{provider_as_source, MemTable};
+use datafusion::prelude::{DataFrame, SessionContext};
+use datafusion_expr::{col, lit, LogicalPlanBuilder};
+use std::sync::Arc;
+use vegafusion_common::arrow::array::RecordBatch;
+use vegafusion_common::arrow::datatypes::{DataType, Field, Schema};
+use sqlparser::ast::{self, visit_expressions_mut};
+use std::ops::ControlFlow;
+use vegafusion_common::column::flat_col;
+use vegafusion_runtime::data::util::DataFrameUtils;
+use vegafusion_runtime::sql::{
+    logical_plan_to_sql, plan_to_cte_sql, AstRewrite, BigQueryDialect, PostgresDialect, SqlDialect,
+};
+
+async fn create_test_dataframe(schema_fields: Vec<Field>) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    create_named_dataframe("test_table", schema_fields).await
+}
+
+async fn create_named_dataframe(
+    table_name: &str,
+    schema_fields: Vec<Field>,
+) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    let ctx = SessionContext::new();
+
+    let schema = Arc::new(Schema::new(schema_fields));
+
+    let empty_batch = RecordBatch::new_empty(schema.clone());
+    let mem_table = MemTable::try_new(schema.clone(), vec![vec![empty_batch]])?;
+
+    let base_plan = LogicalPlanBuilder::scan(
+        table_name,
+        provider_as_source(Arc::new(mem_table)),
+        None,
+    )?
+    .build()?;
+
+    Ok(DataFrame::new(ctx.state(), base_plan))
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_postgres_rewrites_inf_and_nan() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("value", DataType::Float64, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let filtered_df = df.filter(col("value").gt(lit(f64::INFINITY)))?;
+
+    let plan = filtered_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &PostgresDialect)?;
+
+    let expected_sql = "SELECT * FROM test_table WHERE test_table.value > 'Infinity'::double precision";
+
+    assert_eq!(
+        sql, expected_sql,
+        "Postgres dialect should cast non-finite float literals to double precision"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_bigquery_rewrites_row_number() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("value", DataType::Float64, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let indexed_df = df.with_index()?;
+
+    let plan = indexed_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &BigQueryDialect)?;
+
+    assert!(
+        sql.contains("row_number() OVER (ORDER BY NULL)"),
+        "BigQuery dialect has no monotonic-id function, so it should order by a constant: {sql}"
+    );
+
+    Ok(())
+}
+
+/// A dialect with no special float/row-index needs, but that plugs in an `ast_rewrites` pass --
+/// exercising the generic rewrite-pass hooks rather than the fixed `render_float_literal`/
+/// `window_for_row_index` methods.
+struct ShoutingIdentifierDialect;
+
+impl SqlDialect for ShoutingIdentifierDialect {
+    fn window_for_row_index(&self) -> datafusion_expr::Expr {
+        datafusion_expr::Expr::Literal(datafusion_common::ScalarValue::Null)
+    }
+
+    fn render_float_literal(&self, _f: f64) -> Option<String> {
+        None
+    }
+
+    fn ast_rewrites(&self) -> Vec<AstRewrite> {
+        vec![Box::new(|statement: &mut ast::Statement| {
+            visit_expressions_mut(statement, |expr: &mut ast::Expr| {
+                match expr {
+                    ast::Expr::Identifier(ident) => ident.value = ident.value.to_uppercase(),
+                    ast::Expr::CompoundIdentifier(idents) => {
+                        for ident in idents.iter_mut() {
+                            ident.value = ident.value.to_uppercase();
+                        }
+                    }
+                    _ => {}
+                }
+                ControlFlow::<()>::Continue(())
+            });
+        })]
+    }
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_runs_dialect_ast_rewrites() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![Field::new("id", DataType::Int32, false)];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    // Project `id` explicitly: a bare scan unparses to `SELECT *`, which has no identifier
+    // expressions for the rewrite to visit.
+    let projected_df = df.select(vec![col("id")])?;
+    let plan = projected_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &ShoutingIdentifierDialect)?;
+
+    assert!(
+        sql.contains("ID"),
+        "Dialect-supplied ast_rewrites pass should have run: {sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_plan_to_cte_sql_hoists_each_stage() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("customer_name", DataType::Utf8, false),
+        Field::new("customer_age", DataType::Float32, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+
+    // Two back-to-back projections, the same shape that previously needed the fragile
+    // qualifier-stripping workaround to unparse as valid nested SQL.
+    let nested_df = df
+        .select(vec![flat_col("customer_name"), flat_col("customer_age")])?
+        .select(vec![flat_col("customer_name"), flat_col("customer_age")])?;
+
+    let plan = nested_df.logical_plan().clone();
+    let sql = plan_to_cte_sql(&plan, &PostgresDialect)?;
+
+    assert!(sql.starts_with("WITH t0 AS ("), "Expected a WITH clause staging each projection as its own CTE: {sql}");
+    assert!(sql.contains("SELECT * FROM t1"), "Expected the final stage to select from the last materialized CTE: {sql}");
+
+    Ok(())
+}
+
+/// A Postgres-like dialect that also opts into CTE structuring, so `logical_plan_to_sql` takes
+/// the `plan_to_cte_sql` branch instead of the nested-subquery path.
+struct CtePostgresDialect;
+
+impl SqlDialect for CtePostgresDialect {
+    fn window_for_row_index(&self) -> datafusion_expr::Expr {
+        PostgresDialect.window_for_row_index()
+    }
+
+    fn render_float_literal(&self, f: f64) -> Option<String> {
+        PostgresDialect.render_float_literal(f)
+    }
+
+    fn prefers_cte_structuring(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_cte_dialect_still_rewrites_inf_and_nan() -> Result<(), Box<dyn std::error::Error>> {
+    // `prefers_cte_structuring` dialects must not short-circuit past the dialect-generic
+    // row-number/float-literal/ast_rewrites passes -- `plan_to_cte_sql` applies them itself to
+    // every statement it unparses.
+    let schema_fields = vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("value", DataType::Float64, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let filtered_df = df.filter(col("value").gt(lit(f64::INFINITY)))?;
+
+    let plan = filtered_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &CtePostgresDialect)?;
+
+    assert!(
+        sql.contains("'Infinity'::double precision"),
+        "CTE-structured dialects should still get the inf/nan rewrite applied: {sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_plan_to_cte_sql_qualifies_ambiguous_join_columns() -> Result<(), Box<dyn std::error::Error>> {
+    let customers_fields = vec![
+        Field::new("customer_id", DataType::Int32, false),
+        Field::new("customer_name", DataType::Utf8, false),
+    ];
+    let orders_fields = vec![
+        Field::new("customer_id", DataType::Int32, false),
+        Field::new("customer_name", DataType::Utf8, false),
+    ];
+
+    let customers_df = create_test_dataframe(customers_fields).await?;
+    let orders_df = create_named_dataframe("orders_table", orders_fields).await?;
+
+    let joined_df = customers_df.join(
+        orders_df,
+        datafusion::logical_expr::JoinType::Inner,
+        &["customer_id"],
+        &["customer_id"],
+        None,
+    )?;
+
+    // A `Filter` sitting between the `Join` and a wrapping `Projection` over both sides'
+    // `customer_name` columns -- the shape that previously made `alias_relations` stop
+    // recursing at the first non-`Join` node and collapse both relations under one alias.
+    let filtered_df = joined_df.filter(col("customer_id").gt(lit(0)))?;
+    let selected_df = filtered_df.select(vec![
+        flat_col("customer_name"),
+        flat_col("orders_table.customer_name"),
+    ])?;
+
+    let plan = selected_df.logical_plan().clone();
+    let sql = plan_to_cte_sql(&plan, &PostgresDialect)?;
+
+    assert!(
+        sql.contains("t0.customer_name") && sql.contains("t1.customer_name"),
+        "Generated SQL should keep join columns qualified when they would otherwise collide: {sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_folds_constant_arithmetic() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![Field::new("id", DataType::Int32, false)];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let filtered_df = df.filter(col("id").gt(lit(1_i32) + lit(1_i32)))?;
+
+    let plan = filtered_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &PostgresDialect)?;
+
+    assert!(
+        sql.contains("test_table.id > 2"),
+        "Expected the 1 + 1 filter bound to be folded to a single literal: {sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_reconstructs_in_list_predicate() -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![Field::new("id", DataType::Int32, false)];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let filtered_df = df.filter(
+        col("id")
+            .eq(lit(1))
+            .or(col("id").eq(lit(2)))
+            .or(col("id").eq(lit(3))),
+    )?;
+
+    let plan = filtered_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &PostgresDialect)?;
+
+    assert!(
+        sql.contains("test_table.id IN (1, 2, 3)"),
+        "Expected the chain of OR-equalities to collapse into a single IN list: {sql}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logical_plan_to_sql_reconstructs_in_list_predicate_within_compound_and(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schema_fields = vec![
+        Field::new("category", DataType::Utf8, false),
+        Field::new("amount", DataType::Int32, false),
+    ];
+
+    let df = create_test_dataframe(schema_fields).await?;
+    let filtered_df = df.filter(
+        (col("category")
+            .eq(lit("A"))
+            .or(col("category").eq(lit("B"))))
+        .and(col("amount").gt_eq(lit(10))),
+    )?;
+
+    let plan = filtered_df.logical_plan().clone();
+    let sql = logical_plan_to_sql(&plan, &PostgresDialect)?;
+
+    assert!(
+        sql.contains("test_table.category IN ('A', 'B')") && sql.contains("test_table.amount >= 10"),
+        "Expected the OR-of-equality sub-clause to collapse into IN even though it's AND'ed \
+         with another, non-matching condition: {sql}"
+    );
+
+    Ok(())
+}